@@ -1,25 +1,68 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+
 use bevy::{
+    ecs::schedule::ShouldRun,
+    ecs::system::SystemParam,
     prelude::*,
     render::camera::Camera,
     input::mouse::{MouseWheel, MouseMotion},
 };
 use bevy_prototype_lyon::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 fn main() {
+    let scenario = load_scenario();
+
     App::build()
         .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
         .insert_resource(Msaa { samples: 8 })
         .add_plugins(DefaultPlugins)
         .add_plugin(ShapePlugin)
+        .insert_resource(Theta(scenario.theta))
+        .insert_resource(Softening(scenario.softening))
+        .insert_resource(Gravity(scenario.gravity))
+        .insert_resource(Timestep(scenario.dt))
+        .insert_resource(CameraTarget::default())
+        .insert_resource(TrailSettings { max_len: 120, fade: true })
+        .insert_resource(NextBodyParams::default())
+        .insert_resource(Paused(false))
+        .insert_resource(None::<SpawnDrag>)
+        .insert_resource(scenario)
         .add_startup_system(setup.system())
         .add_startup_system(cam_setup.system())
+        .add_startup_system(spawn_panel.system())
         .add_system(cam.system())
+        .add_system(spawn_body_input.system())
+        .add_system(panel_button_system.system())
+        .add_system(update_panel_text.system())
+        .add_system(dump_scenario_input.system())
         .add_system_set(
             SystemSet::new()
+                .with_run_criteria(run_if_not_paused.system())
+                .with_system(
+                    movement
+                        .system()
+                        .label(PhysicsSystem::Movement),
+                )
+                .with_system(
+                    render_trails
+                        .system()
+                        .label(PhysicsSystem::RenderTrails)
+                        .after(PhysicsSystem::Movement),
+                )
+                .with_system(
+                    detect_collisions
+                        .system()
+                        .label(PhysicsSystem::DetectCollisions)
+                        .after(PhysicsSystem::RenderTrails),
+                )
                 .with_system(
                     update_acceleration
                         .system()
-                        .label(PhysicsSystem::UpdateAcceleration),
+                        .label(PhysicsSystem::UpdateAcceleration)
+                        .after(PhysicsSystem::DetectCollisions),
                 )
                 .with_system(
                     update_velocity
@@ -27,12 +70,6 @@ fn main() {
                         .label(PhysicsSystem::UpdateVelocity)
                         .after(PhysicsSystem::UpdateAcceleration),
                 )
-                .with_system(
-                    movement
-                        .system()
-                        .label(PhysicsSystem::Movement)
-                        .after(PhysicsSystem::UpdateVelocity),
-                )
         )
         .run();
 }
@@ -42,11 +79,45 @@ pub enum PhysicsSystem {
     UpdateAcceleration,
     UpdateVelocity,
     Movement,
+    DetectCollisions,
+    RenderTrails,
+}
+
+struct Paused(bool);
+
+fn run_if_not_paused(paused: Res<Paused>) -> ShouldRun {
+    if paused.0 {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
 }
 
 struct Mass(f32);
 struct Velocity(Vec2);
 struct Acceleration(Vec2);
+// Acceleration from the previous step, kept around so update_velocity can
+// average it with the freshly computed one (velocity-Verlet integration).
+struct PrevAccel(Vec2);
+struct Radius(f32);
+struct BodyColor(Color);
+
+// Ring buffer of recent world-space positions, used to draw a fading trail.
+struct Trail {
+    points: VecDeque<Vec2>,
+}
+
+// Entities of the line segments currently drawing this body's trail, reused
+// in place by render_trails instead of being despawned and respawned every
+// frame. Whatever despawns the body is responsible for despawning these too
+// (see despawn_body).
+#[derive(Default)]
+struct TrailSegments(Vec<Entity>);
+
+struct TrailSettings {
+    max_len: usize,
+    fade: bool,
+}
 
 #[derive(Bundle)]
 struct BodyBundle {
@@ -54,19 +125,30 @@ struct BodyBundle {
     transform: Transform,
     velocity: Velocity,
     acceleration: Acceleration,
+    prev_accel: PrevAccel,
+    radius: Radius,
+    color: BodyColor,
+    trail: Trail,
+    trail_segments: TrailSegments,
 }
 
 impl BodyBundle {
-    fn new(mass: f32, pos: Vec2, vel: Vec2) -> Self {
+    fn new(mass: f32, radius: f32, color: Color, pos: Vec2, vel: Vec2) -> Self {
         Self {
             mass: Mass(mass),
             transform: Transform::from_translation(Vec3::new(pos[0], pos[1], 1.0)),
             velocity: Velocity(vel),
             acceleration: Acceleration(Vec2::new(0.0, 0.0)),
+            prev_accel: PrevAccel(Vec2::new(0.0, 0.0)),
+            radius: Radius(radius),
+            color: BodyColor(color),
+            trail: Trail { points: VecDeque::new() },
+            trail_segments: TrailSegments::default(),
         }
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 struct BodyTemplate {
     mass: f32,
     radius: f32,
@@ -89,33 +171,42 @@ impl BodyTemplate {
 
 struct GameCam;
 
-fn setup(
-    mut commands: Commands,
-    // asset_server: Res<AssetServer>,
-    // mut materials: ResMut<Assets<ColorMaterial>>,
-) {
+// Parameters used for the next body spawned via click-drag authoring,
+// edited through the side panel.
+struct NextBodyParams {
+    mass: f32,
+    density: f32,
+    color_index: usize,
+}
+
+impl Default for NextBodyParams {
+    fn default() -> Self {
+        NextBodyParams { mass: 50.0, density: 5.0, color_index: 0 }
+    }
+}
+
+impl NextBodyParams {
+    fn color(&self) -> Color {
+        NEXT_BODY_PALETTE[self.color_index % NEXT_BODY_PALETTE.len()]
+    }
+}
+
+const NEXT_BODY_PALETTE: [Color; 5] =
+    [Color::YELLOW, Color::BLUE, Color::RED, Color::GREEN, Color::WHITE];
+
+// World-space drag in progress while authoring a new body: it will spawn
+// at `start`, with velocity derived from `start` -> `current`.
+struct SpawnDrag {
+    start: Vec2,
+    current: Vec2,
+}
+
+struct DragPreview;
+
+fn setup(mut commands: Commands, scenario: Res<Scenario>) {
     commands.spawn_bundle(OrthographicCameraBundle::new_2d()).insert(GameCam);
-    // commands.spawn_bundle(UiCameraBundle::default());
-
-    // commands
-    //     .spawn_bundle(NodeBundle {
-    //         style: Style {
-    //             size: Size::new(Val::Percent(20.0), Val::Percent(100.0)),
-    //             ..Default::default()
-    //         },
-    //         material: materials.add(Color::DARK_GRAY.into()),
-    //         ..Default::default()
-    //     });
-
-    let bodies = vec![
-        BodyTemplate::new(200.0, 10.0, Color::YELLOW, Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)),
-        BodyTemplate::new(50.0, 5.0, Color::BLUE, Vec2::new(100.0, 0.0), Vec2::new(0.0, -1.0)),
-        BodyTemplate::new(50.0, 5.0, Color::RED, Vec2::new(-100.0, 0.0), Vec2::new(0.0, 1.0)),
-        // BodyTemplate::new(50.0, 5.0, Color::GREEN, Vec2::new(0.0, 350.0), Vec2::new(0.0, 0.0)),
-        // BodyTemplate::new(40.0, 0.0, Color::WHITE, Vec2::new(-80.0, 80.0), Vec2::new(0.0, 0.0)),
-    ];
-
-    for body in bodies.iter() {
+
+    for body in scenario.bodies.iter() {
         commands.spawn_bundle(GeometryBuilder::build_as(
             &shapes::Circle {
                 radius: body.radius,
@@ -130,6 +221,8 @@ fn setup(
             Transform::default(),
         )).insert_bundle(BodyBundle::new(
             body.mass,
+            body.radius,
+            body.color,
             body.pos,
             body.vel,
         ));
@@ -137,35 +230,97 @@ fn setup(
 }
 
 fn cam_setup(
+    scenario: Res<Scenario>,
     mut camera_query: Query<(&mut Camera, &mut Transform)>
 ) {
     for (_cam, mut trans) in camera_query.iter_mut() {
-        trans.scale = Vec3::new(10.0, 10.0, 1.0);
+        trans.scale = Vec3::new(scenario.camera_scale, scenario.camera_scale, 1.0);
     }
 }
 
 const ZOOM_SENSITIVITY: f32 = 0.1;
-const DT: f32 = 1.5;
+
+const SPAWN_BUTTON: MouseButton = MouseButton::Left;
+const VELOCITY_DRAG_SCALE: f32 = 0.05;
+const PANEL_WIDTH_PERCENT: f32 = 20.0;
+const MASS_STEP: f32 = 10.0;
+const DENSITY_STEP: f32 = 1.0;
+
+// At or below this body count the O(n^2) pairwise loop is cheaper than building
+// and walking a quadtree, so we just keep it around as the fallback path.
+const BARNES_HUT_THRESHOLD: usize = 200;
+
+// Below this quad size, stop subdividing and merge coincident/near-coincident
+// bodies into a single point mass instead of recursing forever.
+const MIN_QUAD_SIZE: f32 = 0.01;
+
+struct Theta(f32);
+
+// Plummer softening length: keeps the force finite during close flybys
+// instead of spiking to infinity as the separation approaches zero.
+struct Softening(f32);
+
+// Overall strength of gravity (the "G" in G*m1*m2/r^2); scales every
+// pairwise force uniformly.
+struct Gravity(f32);
+
+// Seconds of simulated time advanced per physics step.
+struct Timestep(f32);
 
 fn update_acceleration(
-    mut query: Query<(Entity, &Mass, &Velocity, &mut Acceleration, &Transform)>
+    mut query: Query<(Entity, &Mass, &Velocity, &mut Acceleration, &mut PrevAccel, &Transform)>,
+    theta: Res<Theta>,
+    softening: Res<Softening>,
+    gravity: Res<Gravity>,
+) {
+    let mut body_count = 0;
+    for (_ent, _mass, _vel, mut acc, mut prev_accel, _trans) in query.iter_mut() {
+        prev_accel.0 = acc.0;
+        acc.0 = Vec2::ZERO;
+        body_count += 1;
+    }
+
+    if body_count <= BARNES_HUT_THRESHOLD {
+        brute_force_accelerations(&mut query, softening.0, gravity.0);
+        return;
+    }
+
+    let snapshot: Vec<(Entity, Vec2, f32)> = query
+        .iter_mut()
+        .map(|(ent, mass, _, _, _, trans)| (ent, trans.translation.truncate(), mass.0))
+        .collect();
+
+    let quad = Quad::bounding(snapshot.iter().map(|(_, pos, _)| *pos));
+    let mut tree = QuadTree::new(quad);
+    for (id, pos, mass) in snapshot.iter() {
+        tree.insert(*id, *pos, *mass);
+    }
+
+    for (ent, mass, _vel, mut acc, _prev_accel, trans) in query.iter_mut() {
+        let pos = trans.translation.truncate();
+        acc.0 += tree.force_on(ent, pos, mass.0, theta.0, softening.0, gravity.0);
+    }
+
+    for (_ent, mass, _vel, mut acc, _prev_accel, _trans) in query.iter_mut() {
+        acc.0 /= mass.0;
+    }
+}
+
+fn brute_force_accelerations(
+    query: &mut Query<(Entity, &Mass, &Velocity, &mut Acceleration, &mut PrevAccel, &Transform)>,
+    softening: f32,
+    gravity: f32,
 ) {
     let mut bodies: Vec<(&Mass, &Transform, Mut<Acceleration>)> = Vec::new();
-    for (_ent, mass, _vel, mut acc, trans) in query.iter_mut() {
+    for (_ent, mass, _vel, mut acc, _prev_accel, trans) in query.iter_mut() {
         for (mass2, trans2, acc2) in bodies.iter_mut() {
             let diff = trans.translation - trans2.translation;
-            // if mass.0 == 101.0 && mass2.0 == 500.0 {
-            //     // info!("a {:?}", diff);
-            //     info!("from {:?} to {:?} -- {:?}, {:?}", mass.0, mass2.0, diff, diff.length_squared());
-            // }
-            if let Some(mut force) = diff.try_normalize() {
-                // if diff.length_squared() > 50.0 {
-                    let magnitude = 1.0 * mass.0 * mass2.0 / diff.length_squared();
-                    force *= magnitude;
-                    let f = Vec2::new(force[0], force[1]);
-                    acc.0 -= f;
-                    acc2.0 += f;
-                // }
+            let denom = (diff.length_squared() + softening * softening).powf(1.5);
+            if denom > 0.0 {
+                let magnitude = gravity * mass.0 * mass2.0 / denom;
+                let f = Vec2::new(diff.x, diff.y) * magnitude;
+                acc.0 -= f;
+                acc2.0 += f;
             }
         }
         bodies.push((mass, trans, acc));
@@ -176,59 +331,1163 @@ fn update_acceleration(
     }
 }
 
-fn update_velocity(mut query: Query<(&mut Velocity, &Acceleration)>) {
-    for (mut vel, acc) in query.iter_mut() {
-        vel.0 += acc.0 * DT;
+// Bounding square of a quadtree node, in world space.
+#[derive(Clone, Copy, Debug)]
+struct Quad {
+    center: Vec2,
+    half_size: f32,
+}
+
+impl Quad {
+    fn bounding(points: impl Iterator<Item = Vec2>) -> Self {
+        let mut min = Vec2::splat(f32::MAX);
+        let mut max = Vec2::splat(f32::MIN);
+        for p in points {
+            min = min.min(p);
+            max = max.max(p);
+        }
+        let center = (min + max) / 2.0;
+        let half_size = ((max - min).max_element() / 2.0).max(1.0);
+        Quad { center, half_size }
+    }
+
+    // 0: top-left, 1: top-right, 2: bottom-left, 3: bottom-right
+    fn quadrant_index(&self, point: Vec2) -> usize {
+        match (point.x >= self.center.x, point.y >= self.center.y) {
+            (false, true) => 0,
+            (true, true) => 1,
+            (false, false) => 2,
+            (true, false) => 3,
+        }
+    }
+
+    fn child(&self, index: usize) -> Quad {
+        let half = self.half_size / 2.0;
+        let offset = match index {
+            0 => Vec2::new(-half, half),
+            1 => Vec2::new(half, half),
+            2 => Vec2::new(-half, -half),
+            _ => Vec2::new(half, -half),
+        };
+        Quad { center: self.center + offset, half_size: half }
+    }
+
+    // Whether `point` falls within this quad's bounds, checked against the
+    // exact (non-accumulating) bounding box rather than a running center of
+    // mass, so it stays reliable however much f32 rounding the tree has seen.
+    fn contains(&self, point: Vec2) -> bool {
+        (point.x - self.center.x).abs() <= self.half_size
+            && (point.y - self.center.y).abs() <= self.half_size
+    }
+}
+
+// Barnes-Hut quadtree: internal nodes cache the total mass and
+// mass-weighted center of mass of everything beneath them, so the force
+// from a whole distant cluster can be approximated as a single point mass.
+enum QuadTree {
+    Empty(Quad),
+    // `ids` holds every entity merged into this leaf: normally just one, but
+    // insert() can fold several bodies into a single leaf (see below), and
+    // force_on needs to recognize all of them to exclude self-force.
+    Leaf { quad: Quad, ids: Vec<Entity>, pos: Vec2, mass: f32 },
+    Internal {
+        quad: Quad,
+        total_mass: f32,
+        center_of_mass: Vec2,
+        children: Box<[QuadTree; 4]>,
+    },
+}
+
+impl QuadTree {
+    fn new(quad: Quad) -> Self {
+        QuadTree::Empty(quad)
+    }
+
+    fn insert(&mut self, id: Entity, pos: Vec2, mass: f32) {
+        match self {
+            QuadTree::Empty(quad) => {
+                *self = QuadTree::Leaf { quad: *quad, ids: vec![id], pos, mass };
+            }
+            QuadTree::Leaf { quad, ids: leaf_ids, pos: leaf_pos, mass: leaf_mass } => {
+                let quad = *quad;
+                if quad.half_size < MIN_QUAD_SIZE || pos == *leaf_pos {
+                    let total_mass = *leaf_mass + mass;
+                    let merged_pos = (*leaf_pos * *leaf_mass + pos * mass) / total_mass;
+                    // Bodies this close together are folded into one leaf,
+                    // since the softened force between them is effectively
+                    // zero anyway -- but every merged id is kept (not just
+                    // the first) so force_on can still recognize any of them
+                    // as "self" and exclude this leaf rather than treating
+                    // it as a distinct, distant mass.
+                    let mut ids = leaf_ids.clone();
+                    ids.push(id);
+                    *self = QuadTree::Leaf { quad, ids, pos: merged_pos, mass: total_mass };
+                    return;
+                }
+                let (leaf_ids, leaf_pos, leaf_mass) = (leaf_ids.clone(), *leaf_pos, *leaf_mass);
+                let mut children = Self::empty_children(quad);
+                // Move the existing leaf down whole: its mass already covers
+                // every merged id, so reinserting each id would count it again.
+                let leaf_index = quad.quadrant_index(leaf_pos);
+                children[leaf_index] = QuadTree::Leaf {
+                    quad: quad.child(leaf_index),
+                    ids: leaf_ids,
+                    pos: leaf_pos,
+                    mass: leaf_mass,
+                };
+                children[quad.quadrant_index(pos)].insert(id, pos, mass);
+                *self = QuadTree::Internal {
+                    quad,
+                    total_mass: leaf_mass + mass,
+                    center_of_mass: (leaf_pos * leaf_mass + pos * mass) / (leaf_mass + mass),
+                    children: Box::new(children),
+                };
+            }
+            QuadTree::Internal { quad, total_mass, center_of_mass, children } => {
+                *center_of_mass = (*center_of_mass * *total_mass + pos * mass) / (*total_mass + mass);
+                *total_mass += mass;
+                let index = quad.quadrant_index(pos);
+                children[index].insert(id, pos, mass);
+            }
+        }
+    }
+
+    fn empty_children(quad: Quad) -> [QuadTree; 4] {
+        [
+            QuadTree::new(quad.child(0)),
+            QuadTree::new(quad.child(1)),
+            QuadTree::new(quad.child(2)),
+            QuadTree::new(quad.child(3)),
+        ]
+    }
+
+    // Force on body `id` (at `pos`/`mass`) from everything in this node,
+    // excluding that body's own contribution so it doesn't attract itself.
+    // Exclusion is by entity identity, not position equality, since two
+    // distinct bodies can sit arbitrarily close together. A node is only
+    // ever treated as a single distant point mass once we know `pos` falls
+    // outside its bounds -- checked against the quad's exact bounding box
+    // rather than its accumulated center of mass, which can drift with f32
+    // rounding across enough insertions to otherwise make a node containing
+    // the query body look "far enough" to approximate.
+    fn force_on(&self, id: Entity, pos: Vec2, mass: f32, theta: f32, softening: f32, gravity: f32) -> Vec2 {
+        match self {
+            QuadTree::Empty(_) => Vec2::ZERO,
+            QuadTree::Leaf { ids: leaf_ids, pos: leaf_pos, mass: leaf_mass, .. } => {
+                if leaf_ids.contains(&id) {
+                    Vec2::ZERO
+                } else {
+                    gravitational_force(pos, mass, *leaf_pos, *leaf_mass, softening, gravity)
+                }
+            }
+            QuadTree::Internal { quad, total_mass, center_of_mass, children } => {
+                let d = (*center_of_mass - pos).length();
+                if !quad.contains(pos) && d > 0.0 && (quad.half_size * 2.0) / d < theta {
+                    gravitational_force(pos, mass, *center_of_mass, *total_mass, softening, gravity)
+                } else {
+                    let mut force = Vec2::ZERO;
+                    for child in children.iter() {
+                        force += child.force_on(id, pos, mass, theta, softening, gravity);
+                    }
+                    force
+                }
+            }
+        }
+    }
+}
+
+// Plummer-softened gravitational force: G*m1*m2 * r_vec / (r^2 + eps^2)^1.5,
+// which stays finite as r -> 0 instead of diverging.
+fn gravitational_force(pos: Vec2, mass: f32, other_pos: Vec2, other_mass: f32, softening: f32, gravity: f32) -> Vec2 {
+    let diff = other_pos - pos;
+    let denom = (diff.length_squared() + softening * softening).powf(1.5);
+    if denom > 0.0 {
+        diff * (gravity * mass * other_mass / denom)
+    } else {
+        Vec2::ZERO
+    }
+}
+
+#[cfg(test)]
+mod quadtree_tests {
+    use super::*;
+
+    // Checks that a query body never attracts itself in a deep tree built
+    // from many bodies. theta = 0.0 means the theta check never passes, so
+    // force_on always recurses down to leaves instead of approximating; the
+    // result is then directly comparable to an O(n^2) brute force that
+    // excludes self by id -- any mismatch means self wasn't excluded.
+    #[test]
+    fn force_on_excludes_self_even_with_many_bodies_on_a_line() {
+        let positions: Vec<Vec2> = (0..251).map(|i| Vec2::new(i as f32, 0.0)).collect();
+        let ids: Vec<Entity> = (0..positions.len() as u32).map(Entity::new).collect();
+        let quad = Quad::bounding(positions.iter().cloned());
+        let mut tree = QuadTree::new(quad);
+        for (id, pos) in ids.iter().zip(positions.iter()) {
+            tree.insert(*id, *pos, 1.0);
+        }
+
+        for (id, pos) in ids.iter().zip(positions.iter()) {
+            let tree_force = tree.force_on(*id, *pos, 1.0, 0.0, 1.0, 1.0);
+
+            let mut expected = Vec2::ZERO;
+            for (other_id, other_pos) in ids.iter().zip(positions.iter()) {
+                if other_id == id {
+                    continue;
+                }
+                expected += gravitational_force(*pos, 1.0, *other_pos, 1.0, 1.0, 1.0);
+            }
+
+            assert!(
+                (tree_force - expected).length() < 1e-3,
+                "body {:?}: expected {:?}, got {:?}",
+                id,
+                expected,
+                tree_force
+            );
+        }
+    }
+
+    // Bodies close enough together get folded into a single QuadTree leaf
+    // (see insert()'s MIN_QUAD_SIZE branch). That leaf must still recognize
+    // every merged body's id as "self", not just the first one inserted, or
+    // the later ones see their own merged mass as a separate nearby body.
+    #[test]
+    fn force_on_excludes_self_for_bodies_merged_into_the_same_leaf() {
+        let a = (Entity::new(0), Vec2::new(0.0, 0.0), 1.0);
+        let b = (Entity::new(1), Vec2::new(0.0005, 0.0), 1.0);
+        let c = (Entity::new(2), Vec2::new(-0.0005, 0.0), 1.0);
+        let far = (Entity::new(3), Vec2::new(50.0, 0.0), 1.0);
+        let bodies = [a, b, c, far];
+
+        let quad = Quad::bounding(bodies.iter().map(|(_, pos, _)| *pos));
+        let mut tree = QuadTree::new(quad);
+        for (id, pos, mass) in bodies.iter() {
+            tree.insert(*id, *pos, *mass);
+        }
+
+        for (id, pos, mass) in [a, b, c].iter() {
+            let force = tree.force_on(*id, *pos, *mass, 0.5, 1.0, 1.0);
+            let expected = gravitational_force(*pos, *mass, far.1, far.2, 1.0, 1.0);
+            assert!(
+                (force - expected).length() < 1e-3,
+                "body {:?}: expected only the far body's pull {:?}, got {:?}",
+                id,
+                expected,
+                force
+            );
+        }
+    }
+
+    #[test]
+    fn force_on_matches_brute_force_for_a_small_cluster() {
+        let bodies = [
+            (Entity::new(0), Vec2::new(0.0, 0.0), 10.0),
+            (Entity::new(1), Vec2::new(5.0, 0.0), 2.0),
+            (Entity::new(2), Vec2::new(-3.0, 4.0), 3.0),
+        ];
+
+        let quad = Quad::bounding(bodies.iter().map(|(_, pos, _)| *pos));
+        let mut tree = QuadTree::new(quad);
+        for (id, pos, mass) in bodies.iter() {
+            tree.insert(*id, *pos, *mass);
+        }
+
+        for (id, pos, mass) in bodies.iter() {
+            let tree_force = tree.force_on(*id, *pos, *mass, 0.0, 1.0, 1.0);
+
+            let mut expected = Vec2::ZERO;
+            for (other_id, other_pos, other_mass) in bodies.iter() {
+                if other_id == id {
+                    continue;
+                }
+                expected += gravitational_force(*pos, *mass, *other_pos, *other_mass, 1.0, 1.0);
+            }
+
+            assert!(
+                (tree_force - expected).length() < 1e-4,
+                "expected {:?}, got {:?}",
+                expected,
+                tree_force
+            );
+        }
+    }
+
+    #[test]
+    fn quad_contains_is_exact_regardless_of_center_of_mass_drift() {
+        let quad = Quad { center: Vec2::new(0.0, 0.0), half_size: 10.0 };
+        assert!(quad.contains(Vec2::new(10.0, 10.0)));
+        assert!(quad.contains(Vec2::new(-10.0, -10.0)));
+        assert!(!quad.contains(Vec2::new(10.1, 0.0)));
+        assert!(!quad.contains(Vec2::new(0.0, -10.1)));
+    }
+}
+
+fn update_velocity(mut query: Query<(&mut Velocity, &Acceleration, &PrevAccel)>, dt: Res<Timestep>) {
+    for (mut vel, acc, prev_accel) in query.iter_mut() {
+        vel.0 += 0.5 * (prev_accel.0 + acc.0) * dt.0;
+    }
+}
+
+fn movement(
+    trail_settings: Res<TrailSettings>,
+    dt: Res<Timestep>,
+    mut query: Query<(&mut Transform, &Velocity, &Acceleration, &mut Trail)>,
+) {
+    for (mut transform, vel, acc, mut trail) in query.iter_mut() {
+        let delta = vel.0 * dt.0 + 0.5 * acc.0 * dt.0 * dt.0;
+        transform.translation += Vec3::new(delta.x, delta.y, 0.0);
+
+        trail.points.push_back(transform.translation.truncate());
+        while trail.points.len() > trail_settings.max_len {
+            trail.points.pop_front();
+        }
+    }
+}
+
+// Updates each body's trail in place as a chain of short line segments,
+// with alpha fading toward the tail so older positions fade out. Segment
+// entities are reused frame to frame (see TrailSegments) rather than being
+// despawned and respawned, since at steady state there can be up to
+// max_len - 1 of them per body.
+fn render_trails(
+    mut commands: Commands,
+    trail_settings: Res<TrailSettings>,
+    mut bodies: Query<(&Trail, &BodyColor, &mut TrailSegments)>,
+) {
+    for (trail, color, mut segments) in bodies.iter_mut() {
+        let points: Vec<Vec2> = trail.points.iter().cloned().collect();
+        let segment_count = points.len().saturating_sub(1);
+
+        while segments.0.len() > segment_count {
+            if let Some(entity) = segments.0.pop() {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        let rgba = color.0.as_rgba_f32();
+        for i in 0..segment_count {
+            let age = (segment_count - 1 - i) as f32 / segment_count as f32;
+            let alpha = if trail_settings.fade { rgba[3] * (1.0 - age) } else { rgba[3] };
+            let segment_color = Color::rgba(rgba[0], rgba[1], rgba[2], alpha);
+
+            let bundle = GeometryBuilder::build_as(
+                &shapes::Line(points[i], points[i + 1]),
+                ShapeColors::new(segment_color),
+                DrawMode::Stroke(StrokeOptions::default()),
+                Transform::default(),
+            );
+
+            match segments.0.get(i) {
+                Some(&entity) => {
+                    commands.entity(entity).insert_bundle(bundle);
+                }
+                None => {
+                    let entity = commands.spawn_bundle(bundle).id();
+                    segments.0.push(entity);
+                }
+            }
+        }
+    }
+}
+
+// Despawns a body along with the trail segment entities it owns. Anything
+// that removes a body outside of render_trails must go through this, or
+// those segments are orphaned for good.
+fn despawn_body(commands: &mut Commands, entity: Entity, segments: &TrailSegments) {
+    commands.entity(entity).despawn();
+    for &segment in segments.0.iter() {
+        commands.entity(segment).despawn();
+    }
+}
+
+// Merges bodies that overlap, conserving mass and momentum. Two bodies
+// collide when the distance between them is less than the sum of their
+// radii; the merged body replaces both.
+fn detect_collisions(
+    mut commands: Commands,
+    query: Query<(Entity, &Mass, &Velocity, &Transform, &Radius, &BodyColor, &TrailSegments)>,
+) {
+    let bodies: Vec<(Entity, f32, Vec2, Vec2, f32, Color)> = query
+        .iter()
+        .map(|(ent, mass, vel, trans, radius, color, _segments)| {
+            (ent, mass.0, trans.translation.truncate(), vel.0, radius.0, color.0)
+        })
+        .collect();
+
+    let mut merged = HashSet::new();
+
+    for i in 0..bodies.len() {
+        let (e1, m1, p1, v1, r1, c1) = bodies[i];
+        if merged.contains(&e1) {
+            continue;
+        }
+
+        for &(e2, m2, p2, v2, r2, c2) in bodies.iter().skip(i + 1) {
+            if merged.contains(&e2) || p1.distance(p2) >= r1 + r2 {
+                continue;
+            }
+
+            let (total_mass, merged_pos, merged_vel, merged_radius, merged_color) =
+                merge_bodies((m1, p1, v1, r1, c1), (m2, p2, v2, r2, c2));
+
+            if let Ok((_, _, _, _, _, _, segments)) = query.get(e1) {
+                despawn_body(&mut commands, e1, segments);
+            }
+            if let Ok((_, _, _, _, _, _, segments)) = query.get(e2) {
+                despawn_body(&mut commands, e2, segments);
+            }
+            merged.insert(e1);
+            merged.insert(e2);
+
+            commands.spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Circle {
+                    radius: merged_radius,
+                    center: merged_pos,
+                    ..shapes::Circle::default()
+                },
+                ShapeColors::outlined(merged_color, merged_color),
+                DrawMode::Outlined {
+                    fill_options: FillOptions::default(),
+                    outline_options: StrokeOptions::default(),
+                },
+                Transform::default(),
+            )).insert_bundle(BodyBundle::new(
+                total_mass,
+                merged_radius,
+                merged_color,
+                merged_pos,
+                merged_vel,
+            ));
+
+            break;
+        }
+    }
+}
+
+// Combines two colliding bodies (mass, pos, vel, radius, color) into one,
+// conserving total mass and momentum.
+fn merge_bodies(
+    (m1, p1, v1, r1, c1): (f32, Vec2, Vec2, f32, Color),
+    (m2, p2, v2, r2, c2): (f32, Vec2, Vec2, f32, Color),
+) -> (f32, Vec2, Vec2, f32, Color) {
+    let total_mass = m1 + m2;
+    let merged_vel = (v1 * m1 + v2 * m2) / total_mass;
+    let merged_pos = (p1 * m1 + p2 * m2) / total_mass;
+    // radius = mass / density, so back out each body's density and
+    // combine them before applying the relationship in reverse.
+    let merged_density = ((m1 / r1) * m1 + (m2 / r2) * m2) / total_mass;
+    let merged_radius = total_mass / merged_density;
+    let merged_color = blend_colors(c1, c2);
+    (total_mass, merged_pos, merged_vel, merged_radius, merged_color)
+}
+
+fn blend_colors(a: Color, b: Color) -> Color {
+    let a = a.as_rgba_f32();
+    let b = b.as_rgba_f32();
+    Color::rgba(
+        (a[0] + b[0]) / 2.0,
+        (a[1] + b[1]) / 2.0,
+        (a[2] + b[2]) / 2.0,
+        (a[3] + b[3]) / 2.0,
+    )
+}
+
+#[cfg(test)]
+mod collision_tests {
+    use super::*;
+
+    #[test]
+    fn merge_bodies_conserves_mass_and_momentum() {
+        let a = (10.0, Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), 2.0, Color::WHITE);
+        let b = (5.0, Vec2::new(10.0, 0.0), Vec2::new(-2.0, 0.0), 1.0, Color::WHITE);
+
+        let (mass, pos, vel, _radius, _color) = merge_bodies(a, b);
+
+        assert!((mass - 15.0).abs() < 1e-6);
+        // momentum before: 10*1 + 5*-2 = 0, so merged velocity should be 0
+        assert!(vel.length() < 1e-6);
+        // center of mass: (10*0 + 5*10) / 15
+        assert!((pos.x - 10.0 / 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn merge_bodies_preserves_density_of_equal_density_bodies() {
+        // Both bodies have density 5.0 (mass / radius); merging same-density
+        // bodies should yield a merged body at that same density.
+        let a = (50.0, Vec2::ZERO, Vec2::ZERO, 10.0, Color::WHITE);
+        let b = (20.0, Vec2::new(1.0, 0.0), Vec2::ZERO, 4.0, Color::WHITE);
+
+        let (mass, _pos, _vel, radius, _color) = merge_bodies(a, b);
+
+        assert!((mass / radius - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn blend_colors_averages_each_channel() {
+        let blended = blend_colors(Color::rgba(0.0, 0.0, 0.0, 0.0), Color::rgba(1.0, 1.0, 1.0, 1.0));
+        let rgba = blended.as_rgba_f32();
+        assert_eq!(rgba, [0.5, 0.5, 0.5, 0.5]);
     }
 }
 
-fn movement(mut query: Query<(&mut Transform, &Velocity)>) {
-    for (mut transform, vel) in query.iter_mut() {
-        transform.translation += Vec3::new(vel.0[0], vel.0[1], 0.0) * DT;
+// How a body is selected: click it to jump to FollowEntity; press the
+// cycle key to toggle between Free and CenterOfMass.
+enum CameraMode {
+    Free,
+    CenterOfMass,
+    FollowEntity(Entity),
+}
+
+struct CameraTarget {
+    mode: CameraMode,
+}
+
+impl Default for CameraTarget {
+    fn default() -> Self {
+        CameraTarget { mode: CameraMode::Free }
     }
 }
 
+const CAMERA_LERP_SPEED: f32 = 0.1;
+const SELECT_BUTTON: MouseButton = MouseButton::Right;
+const CYCLE_MODE_KEY: KeyCode = KeyCode::F;
+
+// Everything `cam` reads to find out what the player did this frame, bundled
+// up so the system itself doesn't need one parameter per input source.
+#[derive(SystemParam)]
+pub struct CamInput<'a> {
+    mouse: Res<'a, Input<MouseButton>>,
+    keyboard: Res<'a, Input<KeyCode>>,
+    motion: EventReader<'a, MouseMotion>,
+    scroll: EventReader<'a, MouseWheel>,
+    windows: Res<'a, Windows>,
+}
+
 fn cam(
-    input_mouse: Res<Input<MouseButton>>,
-    mut ev_motion: EventReader<MouseMotion>,
-    mut ev_scroll: EventReader<MouseWheel>,
+    mut input: CamInput,
+    bodies: Query<(Entity, &Mass, &Transform, &Radius), Without<GameCam>>,
+    mut camera_target: ResMut<CameraTarget>,
     mut query: Query<(&mut Camera, &mut Transform, &GameCam)>
 ) {
-    let pan_button = MouseButton::Left;
+    let pan_button = MouseButton::Middle;
 
     let mut pan = Vec2::ZERO;
     let mut scroll = 0.0;
 
-    if input_mouse.pressed(pan_button) {
-        for ev in ev_motion.iter() {
+    if input.mouse.pressed(pan_button) {
+        for ev in input.motion.iter() {
             pan += ev.delta;
         }
     }
 
-    for ev in ev_scroll.iter() {
+    for ev in input.scroll.iter() {
         scroll -= ev.y * ZOOM_SENSITIVITY;
     }
 
+    if input.keyboard.just_pressed(CYCLE_MODE_KEY) {
+        camera_target.mode = match camera_target.mode {
+            CameraMode::Free => CameraMode::CenterOfMass,
+            CameraMode::CenterOfMass | CameraMode::FollowEntity(_) => CameraMode::Free,
+        };
+    }
+
+    if input.mouse.just_pressed(SELECT_BUTTON) && !cursor_over_panel(&input.windows) {
+        if let Some(cam_transform) = query.iter_mut().next().map(|(_, trans, _)| *trans) {
+            if let Some(world_pos) = cursor_to_world(&input.windows, &cam_transform) {
+                let hit = bodies
+                    .iter()
+                    .find(|(_, _, trans, radius)| trans.translation.truncate().distance(world_pos) <= radius.0);
+                if let Some((entity, _, _, _)) = hit {
+                    camera_target.mode = CameraMode::FollowEntity(entity);
+                }
+            }
+        }
+    }
+
+    let cursor_offset = cursor_screen_offset(&input.windows);
+
     for (mut _cam, mut trans, _gamecam) in query.iter_mut() {
         if scroll.abs() > 0.0 {
             let new_scale = trans.scale + Vec3::new(scroll, scroll, 0.0);
             if new_scale[0] >= 1.0 && new_scale[0] <= 5.0 {
+                // Keep the world point under the cursor fixed as we scale,
+                // instead of always zooming toward the world origin.
+                if let Some(offset) = cursor_offset {
+                    let old_scale = trans.scale;
+                    trans.translation += Vec3::new(
+                        offset.x * (old_scale.x - new_scale.x),
+                        offset.y * (old_scale.y - new_scale.y),
+                        0.0,
+                    );
+                }
                 trans.scale = new_scale;
             }
             // info!("{:?}", trans.scale);
         }
 
-        if pan.length_squared() > 0.0 {
-            let new_translation = Vec3::new(-pan.x * trans.scale[0], pan.y * trans.scale[0], 0.0);
-            trans.translation += new_translation;
-            // info!("{:?} {:?} {:?}", trans.translation, pan.x, pan.y);
+        match camera_target.mode {
+            CameraMode::Free => {
+                if pan.length_squared() > 0.0 {
+                    let new_translation = Vec3::new(-pan.x * trans.scale[0], pan.y * trans.scale[0], 0.0);
+                    trans.translation += new_translation;
+                    // info!("{:?} {:?} {:?}", trans.translation, pan.x, pan.y);
+                }
+            }
+            CameraMode::CenterOfMass => {
+                if let Some(target) = center_of_mass(&bodies) {
+                    let target = Vec3::new(target.x, target.y, trans.translation.z);
+                    trans.translation = trans.translation.lerp(target, CAMERA_LERP_SPEED);
+                }
+            }
+            CameraMode::FollowEntity(entity) => {
+                if let Ok((_, _, body_trans, _)) = bodies.get(entity) {
+                    let target = Vec3::new(
+                        body_trans.translation.x,
+                        body_trans.translation.y,
+                        trans.translation.z,
+                    );
+                    trans.translation = trans.translation.lerp(target, CAMERA_LERP_SPEED);
+                } else {
+                    camera_target.mode = CameraMode::Free;
+                }
+            }
+        }
+    }
+}
+
+fn center_of_mass(bodies: &Query<(Entity, &Mass, &Transform, &Radius), Without<GameCam>>) -> Option<Vec2> {
+    let mut total_mass = 0.0;
+    let mut weighted = Vec2::ZERO;
+    for (_, mass, trans, _) in bodies.iter() {
+        total_mass += mass.0;
+        weighted += trans.translation.truncate() * mass.0;
+    }
+    if total_mass > 0.0 {
+        Some(weighted / total_mass)
+    } else {
+        None
+    }
+}
+
+fn cursor_screen_offset(windows: &Windows) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let window_size = Vec2::new(window.width(), window.height());
+    Some(cursor - window_size / 2.0)
+}
+
+fn cursor_to_world(windows: &Windows, cam_transform: &Transform) -> Option<Vec2> {
+    let offset = cursor_screen_offset(windows)?;
+    Some(cam_transform.translation.truncate() + offset * cam_transform.scale.truncate())
+}
+
+fn cursor_over_panel(windows: &Windows) -> bool {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return false,
+    };
+    match window.cursor_position() {
+        Some(cursor) => cursor.x >= window.width() * (1.0 - PANEL_WIDTH_PERCENT / 100.0),
+        None => false,
+    }
+}
+
+// Click on empty canvas to place a new body at the cursor, drag to set its
+// initial velocity, release to spawn it. A preview line is drawn while
+// dragging so the velocity is visible before it's committed.
+fn spawn_body_input(
+    mut commands: Commands,
+    input_mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    next_body: Res<NextBodyParams>,
+    cam_query: Query<&Transform, With<GameCam>>,
+    mut drag: ResMut<Option<SpawnDrag>>,
+    preview: Query<Entity, With<DragPreview>>,
+) {
+    let cam_transform = match cam_query.iter().next() {
+        Some(trans) => *trans,
+        None => return,
+    };
+    let cursor_world = cursor_to_world(&windows, &cam_transform);
+
+    if input_mouse.just_pressed(SPAWN_BUTTON) {
+        if let (Some(world_pos), false) = (cursor_world, cursor_over_panel(&windows)) {
+            *drag = Some(SpawnDrag { start: world_pos, current: world_pos });
+        }
+    }
+
+    if input_mouse.pressed(SPAWN_BUTTON) {
+        if let (Some(world_pos), Some(active)) = (cursor_world, drag.as_mut()) {
+            active.current = world_pos;
+        }
+    }
+
+    if input_mouse.just_released(SPAWN_BUTTON) {
+        if let Some(active) = drag.take() {
+            let vel = (active.current - active.start) * VELOCITY_DRAG_SCALE;
+            let radius = next_body.mass / next_body.density;
+            let color = next_body.color();
+
+            commands.spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Circle {
+                    radius,
+                    center: active.start,
+                    ..shapes::Circle::default()
+                },
+                ShapeColors::outlined(color, color),
+                DrawMode::Outlined {
+                    fill_options: FillOptions::default(),
+                    outline_options: StrokeOptions::default(),
+                },
+                Transform::default(),
+            )).insert_bundle(BodyBundle::new(
+                next_body.mass,
+                radius,
+                color,
+                active.start,
+                vel,
+            ));
+        }
+    }
+
+    for entity in preview.iter() {
+        commands.entity(entity).despawn();
+    }
+    if let Some(active) = drag.as_ref() {
+        commands.spawn_bundle(GeometryBuilder::build_as(
+            &shapes::Line(active.start, active.current),
+            ShapeColors::new(Color::WHITE),
+            DrawMode::Stroke(StrokeOptions::default()),
+            Transform::default(),
+        )).insert(DragPreview);
+    }
+}
+
+// Which action a panel button performs when clicked.
+enum PanelAction {
+    IncreaseMass,
+    DecreaseMass,
+    IncreaseDensity,
+    DecreaseDensity,
+    CycleColor,
+    TogglePause,
+    Clear,
+}
+
+struct PanelButton(PanelAction);
+
+enum PanelLabel {
+    Mass,
+    Density,
+    Pause,
+}
+
+struct PanelText(PanelLabel);
+
+// Mass, density, and color for the next spawned body are exposed as +/-
+// step buttons and a "Cycle Color" button rather than sliders: bevy 0.5's
+// UI has no slider widget, and building one from scratch (a draggable
+// handle over a track, translating drag position to a value) is out of
+// scope here.
+fn spawn_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.spawn_bundle(UiCameraBundle::default());
+
+    let font: Handle<Font> = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let panel_material = materials.add(Color::DARK_GRAY.into());
+    let button_material = materials.add(Color::rgb(0.25, 0.25, 0.25).into());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(PANEL_WIDTH_PERCENT), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                position: Rect { right: Val::Px(0.0), top: Val::Px(0.0), ..Default::default() },
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: Rect::all(Val::Px(10.0)),
+                ..Default::default()
+            },
+            material: panel_material,
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            spawn_panel_text(parent, &font, PanelLabel::Mass);
+            spawn_panel_button(parent, &font, &button_material, "Mass -", PanelAction::DecreaseMass);
+            spawn_panel_button(parent, &font, &button_material, "Mass +", PanelAction::IncreaseMass);
+            spawn_panel_text(parent, &font, PanelLabel::Density);
+            spawn_panel_button(parent, &font, &button_material, "Density -", PanelAction::DecreaseDensity);
+            spawn_panel_button(parent, &font, &button_material, "Density +", PanelAction::IncreaseDensity);
+            spawn_panel_button(parent, &font, &button_material, "Cycle Color", PanelAction::CycleColor);
+            spawn_panel_text(parent, &font, PanelLabel::Pause);
+            spawn_panel_button(parent, &font, &button_material, "Pause / Resume", PanelAction::TogglePause);
+            spawn_panel_button(parent, &font, &button_material, "Clear", PanelAction::Clear);
+        });
+}
+
+fn spawn_panel_text(parent: &mut ChildBuilder, font: &Handle<Font>, label: PanelLabel) {
+    parent
+        .spawn_bundle(TextBundle {
+            text: Text::with_section(
+                "",
+                TextStyle { font: font.clone(), font_size: 18.0, color: Color::WHITE },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(PanelText(label));
+}
+
+fn spawn_panel_button(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    material: &Handle<ColorMaterial>,
+    label: &str,
+    action: PanelAction,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Px(30.0)),
+                margin: Rect::all(Val::Px(2.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: material.clone(),
+            ..Default::default()
+        })
+        .insert(PanelButton(action))
+        .with_children(|button| {
+            button.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    label,
+                    TextStyle { font: font.clone(), font_size: 16.0, color: Color::WHITE },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        });
+}
+
+fn update_panel_text(
+    next_body: Res<NextBodyParams>,
+    paused: Res<Paused>,
+    mut query: Query<(&mut Text, &PanelText)>,
+) {
+    for (mut text, panel_text) in query.iter_mut() {
+        text.sections[0].value = match panel_text.0 {
+            PanelLabel::Mass => format!("Mass: {:.0}", next_body.mass),
+            PanelLabel::Density => format!("Density: {:.1}", next_body.density),
+            PanelLabel::Pause => if paused.0 { "Paused".to_string() } else { "Running".to_string() },
+        };
+    }
+}
+
+// Panel buttons that were just clicked this frame.
+type ClickedPanelButtons<'w, 's> = Query<'w, (&'s Interaction, &'s PanelButton), (Changed<Interaction>, With<Button>)>;
+
+fn panel_button_system(
+    mut interaction_query: ClickedPanelButtons<'_, '_>,
+    mut next_body: ResMut<NextBodyParams>,
+    mut paused: ResMut<Paused>,
+    mut commands: Commands,
+    bodies: Query<(Entity, &TrailSegments)>,
+) {
+    for (interaction, button) in interaction_query.iter_mut() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button.0 {
+            PanelAction::IncreaseMass => next_body.mass += MASS_STEP,
+            PanelAction::DecreaseMass => next_body.mass = (next_body.mass - MASS_STEP).max(MASS_STEP),
+            PanelAction::IncreaseDensity => next_body.density += DENSITY_STEP,
+            PanelAction::DecreaseDensity => {
+                next_body.density = (next_body.density - DENSITY_STEP).max(DENSITY_STEP)
+            }
+            PanelAction::CycleColor => {
+                next_body.color_index = (next_body.color_index + 1) % NEXT_BODY_PALETTE.len();
+            }
+            PanelAction::TogglePause => paused.0 = !paused.0,
+            PanelAction::Clear => {
+                for (entity, segments) in bodies.iter() {
+                    despawn_body(&mut commands, entity, segments);
+                }
+            }
+        }
+    }
+}
+
+// Path a scenario is loaded from at startup, if present.
+const SCENARIO_PATH: &str = "scenario.ron";
+// Path the live simulation state is written to when DUMP_SCENARIO_KEY is pressed.
+const SCENARIO_DUMP_PATH: &str = "scenario_dump.ron";
+const DUMP_SCENARIO_KEY: KeyCode = KeyCode::S;
+
+// Which built-in preset to fall back to when no scenario.ron is found and
+// no preset was named on the command line.
+const STARTUP_PRESET: Preset = Preset::Default;
+
+enum Preset {
+    Default,
+    FigureEight,
+    SunPlanetMoon,
+    RandomCluster,
+}
+
+impl Preset {
+    // Matches the name passed as the simulator's first CLI argument, e.g.
+    // `cargo run -- figure-eight`, so a preset can be picked at launch
+    // without recompiling.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Preset::Default),
+            "figure-eight" => Some(Preset::FigureEight),
+            "sun-planet-moon" => Some(Preset::SunPlanetMoon),
+            "random-cluster" => Some(Preset::RandomCluster),
+            _ => None,
+        }
+    }
+}
+
+// A complete, shareable description of a simulation: the bodies in it plus
+// the physical constants it was tuned against. Round-trips through RON so
+// a scenario saved on one machine reproduces the same system elsewhere.
+#[derive(Clone, Serialize, Deserialize)]
+struct Scenario {
+    bodies: Vec<BodyTemplate>,
+    dt: f32,
+    gravity: f32,
+    softening: f32,
+    theta: f32,
+    camera_scale: f32,
+}
+
+impl Scenario {
+    fn preset(preset: Preset) -> Self {
+        match preset {
+            Preset::Default => preset_default(),
+            Preset::FigureEight => preset_figure_eight(),
+            Preset::SunPlanetMoon => preset_sun_planet_moon(),
+            Preset::RandomCluster => preset_random_cluster(),
         }
+    }
+}
 
-        // if input_mouse.pressed(pan_button) {
-        //     trans.translation += Vec3::new(1.0, 0.0, 0.0);
-        // }
+fn preset_default() -> Scenario {
+    Scenario {
+        bodies: vec![
+            BodyTemplate::new(200.0, 10.0, Color::YELLOW, Vec2::new(0.0, 0.0), Vec2::new(0.0, 0.0)),
+            BodyTemplate::new(50.0, 5.0, Color::BLUE, Vec2::new(100.0, 0.0), Vec2::new(0.0, -1.0)),
+            BodyTemplate::new(50.0, 5.0, Color::RED, Vec2::new(-100.0, 0.0), Vec2::new(0.0, 1.0)),
+        ],
+        dt: 1.5,
+        gravity: 1.0,
+        softening: 5.0,
+        theta: 0.5,
+        camera_scale: 10.0,
     }
 }
 
-// TODO zoom in on mouse cursor
-//  just get loc of cursor, set cam trans to that on zoom
\ No newline at end of file
+// The Chenciner-Montgomery figure-eight three-body choreography: three
+// equal masses chase each other around a single figure-eight orbit forever.
+// The classic solution is defined for G = m = 1; scaled up here to sit at
+// this sim's usual visual size.
+fn preset_figure_eight() -> Scenario {
+    let mass = 100.0;
+    let pos_scale = 90.0;
+    // With G = 1, velocities scale as sqrt(G * m / L) so the orbit keeps its shape.
+    let vel_scale = f32::sqrt(mass / pos_scale);
+
+    let p1 = Vec2::new(0.970_004_4, -0.243_087_53) * pos_scale;
+    let p2 = -p1;
+    let p3 = Vec2::ZERO;
+
+    let v3 = Vec2::new(-0.932_407_4, -0.864_731_5) * vel_scale;
+    let v1 = -v3 / 2.0;
+    let v2 = v1;
+
+    Scenario {
+        bodies: vec![
+            BodyTemplate::new(mass, 10.0, Color::YELLOW, p1, v1),
+            BodyTemplate::new(mass, 10.0, Color::BLUE, p2, v2),
+            BodyTemplate::new(mass, 10.0, Color::RED, p3, v3),
+        ],
+        dt: 1.5,
+        gravity: 1.0,
+        softening: 5.0,
+        theta: 0.5,
+        camera_scale: 10.0,
+    }
+}
+
+// A heavy sun, a planet in a wide circular orbit, and a moon circling the
+// planet in turn -- a small hierarchy rather than a flat ring of bodies.
+fn preset_sun_planet_moon() -> Scenario {
+    let gravity = 1.0;
+    let sun = BodyTemplate::new(1000.0, 20.0, Color::YELLOW, Vec2::ZERO, Vec2::ZERO);
+
+    let planet_dist = 200.0;
+    let planet_mass = 30.0;
+    let planet_speed = (gravity * sun.mass / planet_dist).sqrt();
+    let planet_pos = Vec2::new(planet_dist, 0.0);
+    let planet_vel = Vec2::new(0.0, planet_speed);
+    let planet = BodyTemplate::new(planet_mass, 8.0, Color::BLUE, planet_pos, planet_vel);
+
+    let moon_dist = 25.0;
+    let moon_speed = (gravity * planet.mass / moon_dist).sqrt();
+    let moon_pos = planet_pos + Vec2::new(0.0, moon_dist);
+    let moon_vel = planet_vel + Vec2::new(-moon_speed, 0.0);
+    let moon = BodyTemplate::new(5.0, 5.0, Color::WHITE, moon_pos, moon_vel);
+
+    Scenario {
+        bodies: vec![sun, planet, moon],
+        dt: 1.0,
+        gravity,
+        softening: 5.0,
+        theta: 0.5,
+        camera_scale: 20.0,
+    }
+}
+
+// A scattering of bodies with randomized mass and position, useful as a
+// quick stress test for the Barnes-Hut path.
+fn preset_random_cluster() -> Scenario {
+    const BODY_COUNT: usize = 300;
+    const CLUSTER_RADIUS: f32 = 400.0;
+
+    let mut rng = rand::thread_rng();
+    let bodies = (0..BODY_COUNT)
+        .map(|_| {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let dist = rng.gen_range(0.0..CLUSTER_RADIUS);
+            let pos = Vec2::new(angle.cos(), angle.sin()) * dist;
+            let mass = rng.gen_range(5.0..40.0);
+            let color = NEXT_BODY_PALETTE[rng.gen_range(0..NEXT_BODY_PALETTE.len())];
+            BodyTemplate::new(mass, 5.0, color, pos, Vec2::ZERO)
+        })
+        .collect();
+
+    Scenario {
+        bodies,
+        dt: 1.5,
+        gravity: 1.0,
+        softening: 5.0,
+        theta: 0.5,
+        camera_scale: 40.0,
+    }
+}
+
+// A preset named explicitly on the command line (e.g. `cargo run --
+// random-cluster`) wins outright; otherwise scenario.ron is loaded from the
+// working directory if present; otherwise STARTUP_PRESET is used. This is
+// how a preset is actually selected at launch without recompiling.
+fn load_scenario() -> Scenario {
+    if let Some(preset) = std::env::args().nth(1).as_deref().and_then(Preset::from_name) {
+        return Scenario::preset(preset);
+    }
+
+    match fs::read_to_string(SCENARIO_PATH) {
+        Ok(contents) => match ron::from_str(&contents) {
+            Ok(scenario) => scenario,
+            Err(err) => {
+                eprintln!("failed to parse {}: {}, falling back to preset", SCENARIO_PATH, err);
+                Scenario::preset(STARTUP_PRESET)
+            }
+        },
+        Err(_) => Scenario::preset(STARTUP_PRESET),
+    }
+}
+
+// Snapshots every live body into a Scenario and writes it to
+// SCENARIO_DUMP_PATH, so the current arrangement can be reloaded later by
+// copying it over SCENARIO_PATH.
+fn dump_scenario_input(
+    input: Res<Input<KeyCode>>,
+    theta: Res<Theta>,
+    softening: Res<Softening>,
+    gravity: Res<Gravity>,
+    dt: Res<Timestep>,
+    camera_query: Query<&Transform, With<GameCam>>,
+    bodies: Query<(&Mass, &Radius, &BodyColor, &Transform, &Velocity)>,
+) {
+    if !input.just_pressed(DUMP_SCENARIO_KEY) {
+        return;
+    }
+
+    let camera_scale = camera_query
+        .iter()
+        .next()
+        .map(|trans| trans.scale.x)
+        .unwrap_or(10.0);
+
+    let scenario = Scenario {
+        bodies: bodies
+            .iter()
+            .map(|(mass, radius, color, trans, vel)| BodyTemplate {
+                mass: mass.0,
+                radius: radius.0,
+                color: color.0,
+                pos: trans.translation.truncate(),
+                vel: vel.0,
+            })
+            .collect(),
+        dt: dt.0,
+        gravity: gravity.0,
+        softening: softening.0,
+        theta: theta.0,
+        camera_scale,
+    };
+
+    match ron::ser::to_string_pretty(&scenario, ron::ser::PrettyConfig::default()) {
+        Ok(serialized) => match fs::write(SCENARIO_DUMP_PATH, serialized) {
+            Ok(()) => info!("wrote live scenario to {}", SCENARIO_DUMP_PATH),
+            Err(err) => eprintln!("failed to write {}: {}", SCENARIO_DUMP_PATH, err),
+        },
+        Err(err) => eprintln!("failed to serialize scenario: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod scenario_tests {
+    use super::*;
+
+    fn assert_round_trips(scenario: Scenario) {
+        let serialized = ron::ser::to_string_pretty(&scenario, ron::ser::PrettyConfig::default())
+            .expect("scenario should serialize to RON");
+        let deserialized: Scenario = ron::from_str(&serialized).expect("RON should parse back into a Scenario");
+
+        assert_eq!(deserialized.bodies.len(), scenario.bodies.len());
+        for (original, round_tripped) in scenario.bodies.iter().zip(deserialized.bodies.iter()) {
+            assert_eq!(original.mass, round_tripped.mass);
+            assert_eq!(original.radius, round_tripped.radius);
+            assert_eq!(original.pos, round_tripped.pos);
+            assert_eq!(original.vel, round_tripped.vel);
+        }
+        assert_eq!(deserialized.dt, scenario.dt);
+        assert_eq!(deserialized.gravity, scenario.gravity);
+        assert_eq!(deserialized.softening, scenario.softening);
+        assert_eq!(deserialized.theta, scenario.theta);
+        assert_eq!(deserialized.camera_scale, scenario.camera_scale);
+    }
+
+    #[test]
+    fn every_builtin_preset_round_trips_through_ron() {
+        assert_round_trips(Scenario::preset(Preset::Default));
+        assert_round_trips(Scenario::preset(Preset::FigureEight));
+        assert_round_trips(Scenario::preset(Preset::SunPlanetMoon));
+        assert_round_trips(Scenario::preset(Preset::RandomCluster));
+    }
+
+    #[test]
+    fn preset_from_name_matches_cli_argument_strings() {
+        assert!(matches!(Preset::from_name("default"), Some(Preset::Default)));
+        assert!(matches!(Preset::from_name("figure-eight"), Some(Preset::FigureEight)));
+        assert!(matches!(Preset::from_name("sun-planet-moon"), Some(Preset::SunPlanetMoon)));
+        assert!(matches!(Preset::from_name("random-cluster"), Some(Preset::RandomCluster)));
+        assert!(Preset::from_name("not-a-real-preset").is_none());
+    }
+}
\ No newline at end of file